@@ -0,0 +1,168 @@
+use anyhow::{Context, Result};
+use image::imageops::FilterType;
+use image::GenericImageView;
+use sha2::{Digest, Sha256};
+
+/// Distance beyond which two images are considered meaningfully different,
+/// rather than just re-encoded/re-compressed versions of the same image.
+const SIMILAR_THRESHOLD: u32 = 5;
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "ico", "jpg", "jpeg", "gif", "webp", "bmp"];
+
+pub fn is_image_path(path: &str) -> bool {
+    std::path::Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| IMAGE_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+pub enum AssetDifference {
+    Image {
+        distance: u32,
+        similar: bool,
+    },
+    Binary {
+        size_delta: i64,
+        before_digest: String,
+        after_digest: String,
+    },
+}
+
+/// Compares two binary assets, returning `None` if they are identical.
+pub fn compare_assets(path: &str, before: &[u8], after: &[u8]) -> Result<Option<AssetDifference>> {
+    if before == after {
+        return Ok(None);
+    }
+
+    if is_image_path(path) {
+        match (difference_hash(before), difference_hash(after)) {
+            (Ok(before_hash), Ok(after_hash)) => {
+                let distance = before_hash.hamming_distance(after_hash);
+
+                return Ok(Some(AssetDifference::Image {
+                    distance,
+                    similar: distance <= SIMILAR_THRESHOLD,
+                }));
+            }
+            (before_result, after_result) => {
+                // A corrupt file or an unsupported format shouldn't abort the
+                // whole comparison; fall back to a binary diff for this file.
+                log::warn!(
+                    "{path}: failed to decode as an image, falling back to binary comparison: {}",
+                    before_result
+                        .err()
+                        .or(after_result.err())
+                        .expect("at least one side failed to decode")
+                );
+            }
+        }
+    }
+
+    Ok(Some(AssetDifference::Binary {
+        size_delta: after.len() as i64 - before.len() as i64,
+        before_digest: sha256_digest(before),
+        after_digest: sha256_digest(after),
+    }))
+}
+
+/// A 64-bit difference hash (dHash) of an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ImageHash(u64);
+
+impl ImageHash {
+    fn hamming_distance(self, other: ImageHash) -> u32 {
+        (self.0 ^ other.0).count_ones()
+    }
+}
+
+/// Computes a difference hash for the given image bytes.
+///
+/// The image is grayscaled and shrunk to 9x8 so that, for each of the 8 rows,
+/// comparing each pixel to its right neighbor yields 64 bits of signal that
+/// survive resizing, re-encoding, and minor compression artifacts.
+fn difference_hash(bytes: &[u8]) -> Result<ImageHash> {
+    let image = image::load_from_memory(bytes).context("failed to decode image")?;
+    let resized = image.grayscale().resize_exact(9, 8, FilterType::Triangle);
+
+    let mut hash = 0u64;
+    let mut bit = 0;
+
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = resized.get_pixel(x, y)[0];
+            let right = resized.get_pixel(x + 1, y)[0];
+
+            if left < right {
+                hash |= 1 << bit;
+            }
+
+            bit += 1;
+        }
+    }
+
+    Ok(ImageHash(hash))
+}
+
+fn sha256_digest(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    format!("{:x}", hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{ImageFormat, Rgb, RgbImage};
+
+    use super::*;
+
+    fn encode_png(width: u32, height: u32, pixel: impl Fn(u32, u32) -> Rgb<u8>) -> Vec<u8> {
+        let image = RgbImage::from_fn(width, height, |x, y| pixel(x, y));
+
+        let mut bytes = Vec::new();
+        image::DynamicImage::ImageRgb8(image)
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .expect("failed to encode test PNG");
+        bytes
+    }
+
+    #[test]
+    fn identical_images_have_zero_distance() {
+        let stripes = encode_png(16, 16, |x, _y| {
+            if x % 2 == 0 {
+                Rgb([0, 0, 0])
+            } else {
+                Rgb([255, 255, 255])
+            }
+        });
+
+        let before = difference_hash(&stripes).unwrap();
+        let after = difference_hash(&stripes).unwrap();
+
+        assert_eq!(before.hamming_distance(after), 0);
+    }
+
+    #[test]
+    fn differing_images_have_nonzero_distance() {
+        let black = encode_png(16, 16, |_x, _y| Rgb([0, 0, 0]));
+        let stripes = encode_png(16, 16, |x, _y| {
+            if x % 2 == 0 {
+                Rgb([0, 0, 0])
+            } else {
+                Rgb([255, 255, 255])
+            }
+        });
+
+        let before = difference_hash(&black).unwrap();
+        let after = difference_hash(&stripes).unwrap();
+
+        assert!(before.hamming_distance(after) > 0);
+    }
+
+    #[test]
+    fn corrupt_bytes_fail_to_decode() {
+        assert!(difference_hash(b"not an image").is_err());
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Build configuration for one side of the comparison (`before` or `after`).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BuildConfig {
+    /// The shell command to run to build the site.
+    pub command: String,
+
+    /// The working directory to run `command` from.
+    ///
+    /// Relative to the directory containing `site-compare.toml`. Defaults to
+    /// that directory.
+    #[serde(default)]
+    pub working_dir: Option<PathBuf>,
+
+    /// Environment variables to set when running `command`.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+
+    /// The directory `command` writes its built site to, relative to
+    /// `working_dir`.
+    pub output_dir: PathBuf,
+}
+
+/// A regex-based rule for normalizing volatile content (cache-busting asset
+/// hashes, build timestamps, generator version strings, etc.) before
+/// comparing two files, so semantically-identical output isn't flagged as
+/// changed.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct NormalizationRule {
+    /// Regex pattern to match in file contents.
+    pub pattern: String,
+
+    /// Replacement text, applied to every match of `pattern`. Supports regex
+    /// capture group references (e.g. `$1`).
+    pub replacement: String,
+}
+
+/// The `site-compare.toml` configuration, describing how to build the two
+/// sites being compared.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Config {
+    pub before: BuildConfig,
+    pub after: BuildConfig,
+
+    /// Rules applied to file contents before comparison, to suppress noise
+    /// from volatile content that doesn't represent a meaningful change.
+    #[serde(default)]
+    pub normalize: Vec<NormalizationRule>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Config> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file: {path:?}"))?;
+
+        toml::from_str(&contents).with_context(|| format!("failed to parse config file: {path:?}"))
+    }
+
+    /// Returns the JSON schema for `site-compare.toml`, for use by editors
+    /// and other tooling.
+    pub fn json_schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(Config)
+    }
+}
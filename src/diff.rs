@@ -0,0 +1,64 @@
+use similar::{ChangeTag, TextDiff};
+
+/// How a single line of a text diff changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffLineKind {
+    Insert,
+    Delete,
+    /// A deleted blank line, tracked separately so it doesn't count towards
+    /// `FileDiff::lines_removed` or get mistaken for a meaningful removal.
+    BlankDelete,
+    Equal,
+}
+
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub text: String,
+}
+
+pub struct FileDiff {
+    pub lines_added: i32,
+    pub lines_removed: i32,
+    pub lines: Vec<DiffLine>,
+}
+
+impl FileDiff {
+    pub fn is_unchanged(&self) -> bool {
+        self.lines_added == 0 && self.lines_removed == 0
+    }
+}
+
+/// Computes a line-level diff between `before` and `after`.
+pub fn diff_text(before: &str, after: &str) -> FileDiff {
+    let diff = TextDiff::from_lines(before, after);
+
+    let mut lines_added = 0;
+    let mut lines_removed = 0;
+    let mut lines = Vec::new();
+
+    for change in diff.iter_all_changes() {
+        let text = change.as_str().unwrap_or_default().to_string();
+        let is_blank_line = text.trim().is_empty();
+
+        let kind = match change.tag() {
+            ChangeTag::Insert => {
+                lines_added += 1;
+                DiffLineKind::Insert
+            }
+            ChangeTag::Delete if is_blank_line => DiffLineKind::BlankDelete,
+            ChangeTag::Delete => {
+                lines_removed += 1;
+                DiffLineKind::Delete
+            }
+            ChangeTag::Equal => DiffLineKind::Equal,
+        };
+
+        lines.push(DiffLine { kind, text });
+    }
+
+    FileDiff {
+        lines_added,
+        lines_removed,
+        lines,
+    }
+}
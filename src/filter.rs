@@ -0,0 +1,85 @@
+use std::path::Path;
+
+use anyhow::Result;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+/// Name of the gitignore-style file used to scope a comparison, read from
+/// the directory containing `site-compare.toml`.
+pub const COMPAREIGNORE_FILENAME: &str = ".compareignore";
+
+/// Decides which collected files are in scope for a comparison, combining a
+/// `.compareignore` file (gitignore semantics) with `--include`/`--exclude`
+/// CLI globs.
+pub struct FileFilter {
+    compareignore: Option<Gitignore>,
+    include: GlobSet,
+    exclude: GlobSet,
+}
+
+impl FileFilter {
+    pub fn new(config_dir: &Path, include: &[String], exclude: &[String]) -> Result<FileFilter> {
+        let compareignore_path = config_dir.join(COMPAREIGNORE_FILENAME);
+        let compareignore = if compareignore_path.exists() {
+            let mut builder = GitignoreBuilder::new(config_dir);
+            if let Some(err) = builder.add(&compareignore_path) {
+                return Err(err.into());
+            }
+            Some(builder.build()?)
+        } else {
+            None
+        };
+
+        Ok(FileFilter {
+            compareignore,
+            include: build_globset(include)?,
+            exclude: build_globset(exclude)?,
+        })
+    }
+
+    /// Returns whether `path` (a site-relative path like `/index.html`)
+    /// should be included in the comparison.
+    pub fn is_included(&self, path: &str) -> bool {
+        let relative = path.trim_start_matches('/');
+
+        if !self.include.is_empty() && !self.include.is_match(relative) {
+            return false;
+        }
+
+        self.matches_dir_rules(relative, false)
+    }
+
+    /// Returns whether `path` (a site-relative directory path) should be
+    /// skipped entirely, pruning the walk before it descends.
+    ///
+    /// Deliberately ignores `--include`, since include globs (e.g.
+    /// `*.html`) describe leaf files, not the directories containing them;
+    /// a directory is only pruned if it's explicitly excluded.
+    pub fn is_dir_excluded(&self, path: &str) -> bool {
+        let relative = path.trim_start_matches('/');
+
+        !self.matches_dir_rules(relative, true)
+    }
+
+    fn matches_dir_rules(&self, relative: &str, is_dir: bool) -> bool {
+        if self.exclude.is_match(relative) {
+            return false;
+        }
+
+        if let Some(compareignore) = &self.compareignore {
+            if compareignore.matched(relative, is_dir).is_ignore() {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+fn build_globset(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern)?);
+    }
+    Ok(builder.build()?)
+}
@@ -0,0 +1,162 @@
+use serde::Serialize;
+
+use crate::assets::AssetDifference;
+use crate::diff::{diff_text, DiffLineKind};
+use crate::{Comparison, Difference};
+
+#[derive(Debug, Serialize)]
+pub struct JsonReport {
+    pub summary: Summary,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<ChangedFileReport>,
+    pub changed_assets: Vec<ChangedAssetReport>,
+}
+
+impl JsonReport {
+    /// Total number of files that differ between before and after, across
+    /// all difference kinds.
+    pub fn difference_count(&self) -> usize {
+        self.added.len() + self.removed.len() + self.changed.len() + self.changed_assets.len()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Summary {
+    pub identical: usize,
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+    pub changed_assets: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChangedFileReport {
+    pub path: String,
+    pub lines_added: i32,
+    pub lines_removed: i32,
+    pub hunks: Vec<DiffLineReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiffLineReport {
+    pub tag: DiffLineTag,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffLineTag {
+    Insert,
+    Delete,
+    Equal,
+}
+
+impl From<DiffLineKind> for DiffLineTag {
+    fn from(kind: DiffLineKind) -> Self {
+        match kind {
+            DiffLineKind::Insert => DiffLineTag::Insert,
+            DiffLineKind::Delete | DiffLineKind::BlankDelete => DiffLineTag::Delete,
+            DiffLineKind::Equal => DiffLineTag::Equal,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChangedAssetReport {
+    pub path: String,
+    #[serde(flatten)]
+    pub difference: AssetDifferenceReport,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AssetDifferenceReport {
+    Image {
+        distance: u32,
+        similar: bool,
+    },
+    Binary {
+        size_delta: i64,
+        before_digest: String,
+        after_digest: String,
+    },
+}
+
+impl From<AssetDifference> for AssetDifferenceReport {
+    fn from(difference: AssetDifference) -> Self {
+        match difference {
+            AssetDifference::Image { distance, similar } => {
+                AssetDifferenceReport::Image { distance, similar }
+            }
+            AssetDifference::Binary {
+                size_delta,
+                before_digest,
+                after_digest,
+            } => AssetDifferenceReport::Binary {
+                size_delta,
+                before_digest,
+                after_digest,
+            },
+        }
+    }
+}
+
+/// Builds a serializable report from a `Comparison`, counting changed lines
+/// the same way the HTML report does.
+pub fn build_json_report(comparison: Comparison) -> JsonReport {
+    let mut identical = comparison.identical;
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    let mut changed = Vec::new();
+    let mut changed_assets = Vec::new();
+
+    for (path, difference) in comparison.differences {
+        match difference {
+            Difference::Added => added.push(path),
+            Difference::Removed => removed.push(path),
+            Difference::AssetChanged(difference) => changed_assets.push(ChangedAssetReport {
+                path,
+                difference: difference.into(),
+            }),
+            Difference::Changed { before, after } => {
+                let file_diff = diff_text(&before, &after);
+
+                if file_diff.is_unchanged() {
+                    identical.insert(path);
+                    continue;
+                }
+
+                changed.push(ChangedFileReport {
+                    path,
+                    lines_added: file_diff.lines_added,
+                    lines_removed: file_diff.lines_removed,
+                    hunks: file_diff
+                        .lines
+                        .into_iter()
+                        .map(|line| DiffLineReport {
+                            tag: line.kind.into(),
+                            text: line.text,
+                        })
+                        .collect(),
+                });
+            }
+        }
+    }
+
+    let summary = Summary {
+        identical: identical.len(),
+        added: added.len(),
+        removed: removed.len(),
+        changed: changed.len(),
+        changed_assets: changed_assets.len(),
+    };
+
+    JsonReport {
+        summary,
+        added,
+        removed,
+        changed,
+        changed_assets,
+    }
+}
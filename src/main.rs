@@ -1,32 +1,116 @@
+mod assets;
+mod config;
+mod diff;
+mod filter;
+mod json_report;
+mod normalize;
 mod report;
+mod server;
+mod watch;
 
 use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::{fs, io};
 
 use anyhow::{bail, Context, Result};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use walkdir::WalkDir;
 
+use crate::assets::{compare_assets, is_image_path, AssetDifference};
+use crate::config::{BuildConfig, Config};
+use crate::filter::FileFilter;
+use crate::json_report::build_json_report;
+use crate::normalize::Normalizer;
 use crate::report::render_report;
+use crate::server::ReportVersion;
+
+/// Address the live-reload server listens on in `--watch` mode.
+const WATCH_SERVER_ADDR: &str = "127.0.0.1:8000";
+
+/// Default path to the config file, relative to the current directory.
+const DEFAULT_CONFIG_PATH: &str = "site-compare.toml";
 
 enum Difference {
     Added,
     Changed { before: String, after: String },
+    AssetChanged(AssetDifference),
     Removed,
 }
 
+/// A collected site file, read either as UTF-8 text or as opaque bytes.
+///
+/// Images and other binary assets can't be meaningfully diffed as text, so
+/// they're kept around as raw bytes and compared separately in
+/// `compare_assets`.
+enum CollectedFile {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl CollectedFile {
+    fn as_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        match self {
+            CollectedFile::Text(contents) => std::borrow::Cow::Borrowed(contents.as_bytes()),
+            CollectedFile::Binary(bytes) => std::borrow::Cow::Borrowed(bytes),
+        }
+    }
+}
+
 struct Comparison {
     pub identical: BTreeSet<String>,
     pub differences: BTreeMap<String, Difference>,
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Html,
+    Json,
+}
+
 #[derive(Parser)]
 struct Args {
+    /// Path to the `site-compare.toml` config file describing how to build
+    /// the before/after sites.
+    #[clap(long, default_value = DEFAULT_CONFIG_PATH)]
+    config: PathBuf,
+
+    /// Output format for the report.
+    #[clap(long, value_enum, default_value_t = Format::Html)]
+    format: Format,
+
+    /// Where to write the report. Defaults to `.compare/report.html` for
+    /// `--format html`, or stdout for `--format json`.
+    #[clap(long)]
+    output: Option<PathBuf>,
+
+    /// Exit non-zero if more than this many files differ (added, removed,
+    /// changed, or changed assets). Lets a CI pipeline gate a deploy on the
+    /// before/after sites matching.
+    #[clap(long, default_value_t = 0)]
+    fail_on: usize,
+
     /// Whether to open the report in the browser after running.
     #[clap(long)]
     open: bool,
+
+    /// Watch the site source for changes, rebuilding and re-diffing on each
+    /// change, and serve the report with live reload.
+    #[clap(long)]
+    watch: bool,
+
+    /// Print the JSON schema for `site-compare.toml` and exit.
+    #[clap(long)]
+    print_schema: bool,
+
+    /// Glob patterns for files to include in the comparison. If given, only
+    /// matching files are considered.
+    #[clap(long)]
+    include: Vec<String>,
+
+    /// Glob patterns for files to exclude from the comparison.
+    #[clap(long)]
+    exclude: Vec<String>,
 }
 
 fn main() -> Result<()> {
@@ -36,84 +120,243 @@ fn main() -> Result<()> {
 
     let args = Args::parse();
 
+    if args.print_schema {
+        println!("{}", serde_json::to_string_pretty(&Config::json_schema())?);
+        return Ok(());
+    }
+
+    let config = Config::load(&args.config)
+        .with_context(|| format!("failed to load config: {:?}", args.config))?;
+    let config_dir = args
+        .config
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."))
+        .to_path_buf();
+
+    let normalizer =
+        Normalizer::new(&config.normalize).context("failed to compile normalization rules")?;
+    let filter = FileFilter::new(&config_dir, &args.include, &args.exclude)
+        .context("failed to compile include/exclude filters")?;
+
     let compare_dir = PathBuf::from(".compare");
-    let before_dirname = "before";
-    let after_dirname = "after";
-    let before_dir = compare_dir.join(before_dirname);
-    let after_dir = compare_dir.join(after_dirname);
-    let both_dirs = [&before_dir, &after_dir];
-
-    for output_dir in &both_dirs {
-        log::info!("Removing output directory {output_dir:?}");
-        if let Err(err) = fs::remove_dir_all(output_dir) {
-            if err.kind() != io::ErrorKind::NotFound {
-                return Err(err.into());
+
+    let comparison = compare_once(&compare_dir, &config_dir, &config, &normalizer, &filter)?;
+    let (report_path, differences) = write_report(
+        comparison,
+        args.format,
+        &compare_dir,
+        args.output.as_deref(),
+    )?;
+
+    if args.watch {
+        let version = ReportVersion::default();
+
+        let server_started = if let Some(report_path) = &report_path {
+            match server::bind(WATCH_SERVER_ADDR) {
+                Ok(server) => {
+                    let report_path = report_path.clone();
+                    let version = version.clone();
+                    std::thread::spawn(move || {
+                        if let Err(err) =
+                            server::serve(server, report_path, version, WATCH_SERVER_ADDR)
+                        {
+                            log::error!("live-reload server failed: {err}");
+                        }
+                    });
+                    true
+                }
+                Err(err) => {
+                    log::error!("failed to start live-reload server: {err}");
+                    false
+                }
+            }
+        } else {
+            false
+        };
+
+        if args.open {
+            if server_started {
+                opener::open(format!("http://{WATCH_SERVER_ADDR}"))?;
+            } else {
+                log::warn!(
+                    "--open has no effect in --watch mode without a running live-reload server"
+                );
             }
         }
+
+        // `before`/`after` may write their output anywhere under their
+        // working directory (e.g. a Zola site's `public/` next to the
+        // watched root). Without excluding those dirs, each rebuild's own
+        // writes would be picked back up as source changes and the watch
+        // loop would never settle.
+        let exclude = [
+            compare_dir.clone(),
+            PathBuf::from("target"),
+            build_output_dir(&config_dir, &config.before),
+            build_output_dir(&config_dir, &config.after),
+        ];
+
+        return watch::watch(Path::new("."), &exclude, || {
+            let comparison =
+                compare_once(&compare_dir, &config_dir, &config, &normalizer, &filter)?;
+            write_report(
+                comparison,
+                args.format,
+                &compare_dir,
+                args.output.as_deref(),
+            )?;
+            version.bump();
+            Ok(())
+        });
+    }
+
+    if args.open {
+        if let Some(report_path) = &report_path {
+            opener::open(report_path)?;
+        }
+    }
+
+    if differences > args.fail_on {
+        log::warn!(
+            "{differences} file(s) differ, exceeding --fail-on threshold of {}",
+            args.fail_on
+        );
+        std::process::exit(1);
     }
 
+    Ok(())
+}
+
+/// Builds both sites and compares their output.
+fn compare_once(
+    compare_dir: &Path,
+    config_dir: &Path,
+    config: &Config,
+    normalizer: &Normalizer,
+    filter: &FileFilter,
+) -> Result<Comparison> {
     log::info!("Building before site");
-    build_before_site(&before_dir).context("failed to build before site")?;
+    let before_dir = run_build("before", config_dir, &config.before)?;
 
     log::info!("Building after site");
-    build_after_site(&after_dir).context("failed to build after site")?;
+    let after_dir = run_build("after", config_dir, &config.after)?;
 
-    setup_prettier(&compare_dir).context("failed to setup Prettier")?;
+    setup_prettier(compare_dir).context("failed to setup Prettier")?;
 
-    for output_dir in [before_dirname, after_dirname] {
+    for output_dir in [&before_dir, &after_dir] {
         log::info!("Formatting {output_dir:?} with Prettier");
-        format_with_prettier(&compare_dir, output_dir)
+        format_with_prettier(compare_dir, output_dir)
             .with_context(|| format!("failed to format {output_dir:?} with Prettier"))?;
     }
 
     log::info!("Collecting before site files");
-    let before_site = collect_files(&before_dir).context("failed to collect before site files")?;
+    let before_site =
+        collect_files(&before_dir, filter).context("failed to collect before site files")?;
 
     log::info!("Collecting after site files");
-    let after_site = collect_files(&after_dir).context("failed to collect after site files")?;
+    let after_site =
+        collect_files(&after_dir, filter).context("failed to collect after site files")?;
 
     log::info!("Comparing before and after");
-    let comparison = compare_sites(before_site, after_site)?;
+    compare_sites(before_site, after_site, normalizer)
+}
 
-    log::info!("Generating report");
-    let report = render_report(comparison).context("failed to render report")?;
+/// Renders a `Comparison` in the requested format and writes it out,
+/// returning the path it was written to (if any) and the total number of
+/// files that differ.
+fn write_report(
+    comparison: Comparison,
+    format: Format,
+    compare_dir: &Path,
+    output: Option<&Path>,
+) -> Result<(Option<PathBuf>, usize)> {
+    match format {
+        Format::Html => {
+            // Count files the same way the report does: a changed file whose
+            // line diff comes out empty is listed as identical.
+            let differences = comparison
+                .differences
+                .values()
+                .filter(|difference| match difference {
+                    Difference::Changed { before, after } => {
+                        !diff::diff_text(before, after).is_unchanged()
+                    }
+                    _ => true,
+                })
+                .count();
+
+            log::info!("Generating report");
+            let report = render_report(comparison).context("failed to render report")?;
+
+            fs::create_dir_all(compare_dir)?;
+            let report_path = output
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| compare_dir.join("report.html"));
+            fs::write(&report_path, &report).context("failed to write report to file")?;
+            log::info!("Report written to {:?}", report_path);
+
+            Ok((Some(report_path), differences))
+        }
+        Format::Json => {
+            let json_report = build_json_report(comparison);
+            let differences = json_report.difference_count();
+            let json = serde_json::to_string_pretty(&json_report)?;
+
+            match output {
+                Some(path) => {
+                    fs::write(path, &json)
+                        .with_context(|| format!("failed to write report to {path:?}"))?;
+                    log::info!("Report written to {path:?}");
+                    Ok((Some(path.to_path_buf()), differences))
+                }
+                None => {
+                    println!("{json}");
+                    Ok((None, differences))
+                }
+            }
+        }
+    }
+}
 
-    let report_path = compare_dir.join("report.html");
-    fs::write(&report_path, report).context("failed to write report to file")?;
-    log::info!("Report written to {:?}", report_path);
+/// Runs a side's build command and returns the directory it wrote its
+/// output to.
+fn run_build(label: &str, config_dir: &Path, build: &BuildConfig) -> Result<PathBuf> {
+    let working_dir = build_working_dir(config_dir, build);
+    let output_dir = build_output_dir(config_dir, build);
 
-    if args.open {
-        opener::open(report_path)?;
+    log::info!("Removing output directory {output_dir:?}");
+    if let Err(err) = fs::remove_dir_all(&output_dir) {
+        if err.kind() != std::io::ErrorKind::NotFound {
+            return Err(err).with_context(|| format!("failed to clean {output_dir:?}"));
+        }
     }
 
-    Ok(())
-}
-
-fn build_before_site(output_dir: &Path) -> Result<()> {
-    let status = Command::new("nix-shell")
-        .args(["--command"])
-        .arg(format!(
-            "zola build --output-dir {}",
-            output_dir.to_string_lossy()
-        ))
-        .status()?;
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(&build.command)
+        .current_dir(&working_dir)
+        .envs(&build.env)
+        .status()
+        .with_context(|| format!("failed to run {label} command: {}", build.command))?;
     if !status.success() {
-        bail!("failed with status: {status}");
+        bail!("{label} build failed with status: {status}");
     }
 
-    Ok(())
+    Ok(output_dir)
 }
 
-fn build_after_site(output_dir: &Path) -> Result<()> {
-    let status = Command::new("cargo")
-        .args(["run", "--package", "site", "--", "build", "--output-dir"])
-        .arg(output_dir)
-        .status()?;
-    if !status.success() {
-        bail!("failed with status: {status}");
+/// The directory a build command runs from.
+fn build_working_dir(config_dir: &Path, build: &BuildConfig) -> PathBuf {
+    match &build.working_dir {
+        Some(dir) => config_dir.join(dir),
+        None => config_dir.to_path_buf(),
     }
+}
 
-    Ok(())
+/// The directory a build command writes its output to.
+fn build_output_dir(config_dir: &Path, build: &BuildConfig) -> PathBuf {
+    build_working_dir(config_dir, build).join(&build.output_dir)
 }
 
 fn setup_prettier(work_dir: &Path) -> Result<()> {
@@ -155,11 +398,10 @@ fn setup_prettier(work_dir: &Path) -> Result<()> {
     Ok(())
 }
 
-fn format_with_prettier(prettier_dir: &Path, dirname: &str) -> Result<()> {
-    let status = Command::new("node_modules/.bin/prettier")
-        .arg(dirname)
+fn format_with_prettier(prettier_dir: &Path, output_dir: &Path) -> Result<()> {
+    let status = Command::new(prettier_dir.join("node_modules/.bin/prettier"))
+        .arg(output_dir)
         .arg("--write")
-        .current_dir(prettier_dir)
         .status()?;
     if !status.success() {
         bail!("failed with status: {status}");
@@ -168,8 +410,27 @@ fn format_with_prettier(prettier_dir: &Path, dirname: &str) -> Result<()> {
     Ok(())
 }
 
-fn collect_files(dir: &Path) -> Result<BTreeMap<String, String>> {
-    let walker = WalkDir::new(dir).into_iter();
+fn collect_files(dir: &Path, filter: &FileFilter) -> Result<BTreeMap<String, CollectedFile>> {
+    // Prune excluded directories before WalkDir descends into them, so a
+    // rule excluding e.g. a generated search index skips the traversal
+    // entirely rather than just the files it would have recorded.
+    let walker = WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(|entry| {
+            if !entry.file_type().is_dir() {
+                return true;
+            }
+
+            let Ok(relative) = entry.path().strip_prefix(dir) else {
+                return true;
+            };
+            if relative.as_os_str().is_empty() {
+                return true;
+            }
+
+            let site_path = format!("/{}", relative.to_string_lossy());
+            !filter.is_dir_excluded(&site_path)
+        });
 
     let mut files = BTreeMap::new();
 
@@ -183,18 +444,27 @@ fn collect_files(dir: &Path) -> Result<BTreeMap<String, String>> {
         };
 
         if !path.is_dir() {
-            if filename.ends_with(".png") || filename.ends_with(".ico") {
-                log::warn!("Skipping file: {path:?}");
+            let site_path = path.strip_prefix(dir)?.to_string_lossy().to_string();
+            let site_path = format!("/{site_path}");
+
+            if !filter.is_included(&site_path) {
                 continue;
             }
 
-            let contents = fs::read_to_string(&path)
-                .with_context(|| format!("failed to read to string: {path:?}"))?;
-            let path = path.strip_prefix(dir)?.to_string_lossy().to_string();
-            let path = format!("/{path}");
+            let file = if is_image_path(filename) {
+                CollectedFile::Binary(
+                    fs::read(&path).with_context(|| format!("failed to read: {path:?}"))?,
+                )
+            } else {
+                match fs::read_to_string(&path) {
+                    Ok(contents) => CollectedFile::Text(contents),
+                    Err(_) => CollectedFile::Binary(
+                        fs::read(&path).with_context(|| format!("failed to read: {path:?}"))?,
+                    ),
+                }
+            };
 
-            files.insert(path, contents);
-        } else {
+            files.insert(site_path, file);
         }
     }
 
@@ -202,27 +472,44 @@ fn collect_files(dir: &Path) -> Result<BTreeMap<String, String>> {
 }
 
 fn compare_sites(
-    before: BTreeMap<String, String>,
-    after: BTreeMap<String, String>,
+    before: BTreeMap<String, CollectedFile>,
+    after: BTreeMap<String, CollectedFile>,
+    normalizer: &Normalizer,
 ) -> Result<Comparison> {
     let mut identical = BTreeSet::new();
     let mut differences = BTreeMap::new();
 
-    for (path, before_content) in before.iter() {
+    for (path, before_file) in before.iter() {
         match after.get(path) {
-            Some(after_content) => {
-                if after_content != before_content {
-                    differences.insert(
-                        path.clone(),
-                        Difference::Changed {
-                            before: before_content.clone(),
-                            after: after_content.clone(),
-                        },
-                    );
-                } else {
-                    identical.insert(path.clone());
+            Some(after_file) => match (before_file, after_file) {
+                (CollectedFile::Text(before_content), CollectedFile::Text(after_content)) => {
+                    let normalized_before = normalizer.normalize(before_content);
+                    let normalized_after = normalizer.normalize(after_content);
+
+                    if normalized_after != normalized_before {
+                        differences.insert(
+                            path.clone(),
+                            Difference::Changed {
+                                before: before_content.clone(),
+                                after: after_content.clone(),
+                            },
+                        );
+                    } else {
+                        identical.insert(path.clone());
+                    }
                 }
-            }
+                (before_file, after_file) => {
+                    match compare_assets(path, &before_file.as_bytes(), &after_file.as_bytes())? {
+                        Some(asset_difference) => {
+                            differences
+                                .insert(path.clone(), Difference::AssetChanged(asset_difference));
+                        }
+                        None => {
+                            identical.insert(path.clone());
+                        }
+                    }
+                }
+            },
             None => {
                 differences.insert(path.clone(), Difference::Removed);
             }
@@ -0,0 +1,75 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+
+use crate::config::NormalizationRule;
+
+/// Applies a set of regex→replacement rules to file contents before
+/// comparison, so volatile content (cache-busting hashes, timestamps,
+/// generator versions) doesn't get flagged as a meaningful change.
+pub struct Normalizer {
+    rules: Vec<(Regex, String)>,
+}
+
+impl Normalizer {
+    pub fn new(rules: &[NormalizationRule]) -> Result<Normalizer> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                let regex = Regex::new(&rule.pattern)
+                    .with_context(|| format!("invalid normalization pattern: {}", rule.pattern))?;
+                Ok((regex, rule.replacement.clone()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Normalizer { rules })
+    }
+
+    /// Applies all normalization rules to `content`, in order.
+    pub fn normalize(&self, content: &str) -> String {
+        let mut normalized = content.to_string();
+
+        for (pattern, replacement) in &self.rules {
+            normalized = pattern
+                .replace_all(&normalized, replacement.as_str())
+                .into_owned();
+        }
+
+        normalized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, replacement: &str) -> NormalizationRule {
+        NormalizationRule {
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+        }
+    }
+
+    #[test]
+    fn applies_a_single_rule() {
+        let normalizer = Normalizer::new(&[rule(r"\d+", "N")]).unwrap();
+
+        assert_eq!(normalizer.normalize("build-42"), "build-N");
+    }
+
+    #[test]
+    fn later_rules_see_earlier_rules_output() {
+        let normalizer = Normalizer::new(&[rule(r"\d+", "N"), rule(r"N-N", "NN")]).unwrap();
+
+        assert_eq!(normalizer.normalize("build-42-17"), "build-NN");
+    }
+
+    #[test]
+    fn rule_order_changes_the_result() {
+        let collapse_first = Normalizer::new(&[rule(r"foo", "bar"), rule(r"bar", "baz")]).unwrap();
+        let collapse_second =
+            Normalizer::new(&[rule(r"bar", "baz"), rule(r"foo", "bar")]).unwrap();
+
+        assert_eq!(collapse_first.normalize("foo"), "baz");
+        assert_eq!(collapse_second.normalize("foo"), "bar");
+    }
+}
@@ -2,9 +2,10 @@ use anyhow::Result;
 use auk::renderer::HtmlElementRenderer;
 use auk::*;
 use pulldown_cmark::{self as md};
-use similar::{ChangeTag, TextDiff};
 use slug::slugify;
 
+use crate::assets::AssetDifference;
+use crate::diff::{diff_text, DiffLineKind};
 use crate::{Comparison, Difference};
 
 struct ChangedFile {
@@ -14,10 +15,16 @@ struct ChangedFile {
     diff_lines: Vec<HtmlElement>,
 }
 
+struct ChangedAsset {
+    path: String,
+    difference: AssetDifference,
+}
+
 pub fn render_report(comparison: Comparison) -> Result<String> {
     let mut identical = comparison.identical;
     let mut added = Vec::new();
     let mut changed = Vec::new();
+    let mut changed_assets = Vec::new();
     let mut removed = Vec::new();
 
     let mut total_lines_added = 0;
@@ -27,60 +34,51 @@ pub fn render_report(comparison: Comparison) -> Result<String> {
         match difference {
             Difference::Added => added.push(path),
             Difference::Changed { before, after } => {
-                let diff = TextDiff::from_lines(&before, &after);
-
-                let mut lines_added = 0;
-                let mut lines_removed = 0;
-                let mut lines = Vec::new();
-
-                for change in diff.iter_all_changes() {
-                    let is_blank_line = change.as_str().unwrap().trim().is_empty();
-
-                    let (sign, class) = match change.tag() {
-                        ChangeTag::Insert => {
-                            lines_added += 1;
-                            ("+", Some("diff-line diff-add"))
-                        }
-                        ChangeTag::Delete => {
-                            if is_blank_line {
-                                ("~", Some("diff-line diff-blank-line"))
-                            } else {
-                                lines_removed += 1;
-                                ("-", Some("diff-line diff-remove"))
-                            }
-                        }
-                        ChangeTag::Equal => (" ", None),
-                    };
-
-                    lines.push(
-                        span()
-                            .class::<&str>(class)
-                            .child(escape_html(&format!("{sign}{change}"))),
-                    )
-                }
+                let file_diff = diff_text(&before, &after);
 
-                if lines_added == 0 && lines_removed == 0 {
+                if file_diff.is_unchanged() {
                     identical.insert(path.clone());
                     continue;
                 }
 
-                total_lines_added += lines_added;
-                total_lines_removed += lines_removed;
+                total_lines_added += file_diff.lines_added;
+                total_lines_removed += file_diff.lines_removed;
+
+                let diff_lines = file_diff
+                    .lines
+                    .into_iter()
+                    .map(|line| {
+                        let (sign, class) = match line.kind {
+                            DiffLineKind::Insert => ("+", Some("diff-line diff-add")),
+                            DiffLineKind::Delete => ("-", Some("diff-line diff-remove")),
+                            DiffLineKind::BlankDelete => ("~", Some("diff-line diff-blank-line")),
+                            DiffLineKind::Equal => (" ", None),
+                        };
+
+                        span()
+                            .class::<&str>(class)
+                            .child(escape_html(&format!("{sign}{}", line.text)))
+                    })
+                    .collect();
 
                 changed.push(ChangedFile {
                     path,
-                    lines_added,
-                    lines_removed,
-                    diff_lines: lines,
+                    lines_added: file_diff.lines_added,
+                    lines_removed: file_diff.lines_removed,
+                    diff_lines,
                 })
             }
+            Difference::AssetChanged(difference) => {
+                changed_assets.push(ChangedAsset { path, difference })
+            }
             Difference::Removed => removed.push(path),
         }
     }
 
     let percent_similar = {
         let identical_files = identical.len();
-        let total_files = identical_files + added.len() + changed.len() + removed.len();
+        let total_files =
+            identical_files + added.len() + changed.len() + changed_assets.len() + removed.len();
         ((identical_files as f64 / total_files as f64) * 100.0).round() as u32
     };
 
@@ -149,6 +147,18 @@ pub fn render_report(comparison: Comparison) -> Result<String> {
                             )
                         }))),
                 )
+                .child(
+                    div()
+                        .child(h2().child(format!("Changed assets ({})", changed_assets.len())))
+                        .child(ol().children(changed_assets.iter().map(|asset| {
+                            li().child(
+                                div()
+                                    .class("flex items-center gap1")
+                                    .child(code().child(&asset.path))
+                                    .child(asset_difference_indicator(&asset.difference)),
+                            )
+                        }))),
+                )
                 .child(
                     div()
                         .child(
@@ -210,6 +220,29 @@ fn diff_remove_indicator(lines_removed: i32) -> HtmlElement {
         .child(format!("-{lines_removed}"))
 }
 
+fn asset_difference_indicator(difference: &AssetDifference) -> HtmlElement {
+    match difference {
+        AssetDifference::Image { distance, similar } => {
+            let class = if *similar {
+                "code diff-indicator diff-similar"
+            } else {
+                "code diff-indicator diff-remove"
+            };
+
+            span().class(class).child(format!("Δ{distance}"))
+        }
+        AssetDifference::Binary { size_delta, .. } => {
+            let class = if *size_delta >= 0 {
+                "code diff-indicator diff-add"
+            } else {
+                "code diff-indicator diff-remove"
+            };
+
+            span().class(class).child(format!("{size_delta:+}B"))
+        }
+    }
+}
+
 fn diff_indicator(lines_added: i32, lines_removed: i32) -> HtmlElement {
     span()
         .class("flex gap1")
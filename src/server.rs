@@ -0,0 +1,87 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use tiny_http::{Header, Response, Server};
+
+const LIVE_RELOAD_SCRIPT: &str = r#"
+<script>
+(function () {
+    let lastVersion = null;
+    setInterval(async () => {
+        try {
+            const response = await fetch('/__site-compare/version');
+            const version = await response.text();
+            if (lastVersion === null) {
+                lastVersion = version;
+            } else if (version !== lastVersion) {
+                location.reload();
+            }
+        } catch (err) {
+            // The server is probably mid-rebuild; try again on the next tick.
+        }
+    }, 1000);
+})();
+</script>
+"#;
+
+/// A counter bumped every time the report is regenerated, so the browser can
+/// poll for it and reload itself.
+#[derive(Clone, Default)]
+pub struct ReportVersion(Arc<AtomicU64>);
+
+impl ReportVersion {
+    pub fn bump(&self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn get(&self) -> u64 {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Binds the live-reload server's listening socket.
+///
+/// Split out from `serve` so the caller can confirm the server actually
+/// started (e.g. the port wasn't already in use) before doing anything that
+/// assumes it's reachable, such as opening a browser tab at its address.
+pub fn bind(addr: &str) -> Result<Server> {
+    Server::http(addr).map_err(|err| anyhow!("failed to start server: {err}"))
+}
+
+/// Serves `report_path` with an injected live-reload script on an
+/// already-bound `server`, blocking forever.
+pub fn serve(server: Server, report_path: PathBuf, version: ReportVersion, addr: &str) -> Result<()> {
+    log::info!("Serving report at http://{addr}");
+
+    for request in server.incoming_requests() {
+        let response = if request.url() == "/__site-compare/version" {
+            Response::from_string(version.get().to_string())
+        } else {
+            let html = fs::read_to_string(&report_path).unwrap_or_default();
+            Response::from_string(inject_live_reload(&html)).with_header(
+                Header::from_bytes(&b"Content-Type"[..], &b"text/html; charset=utf-8"[..])
+                    .expect("static header is valid"),
+            )
+        };
+
+        if let Err(err) = request.respond(response) {
+            log::warn!("failed to respond to request: {err}");
+        }
+    }
+
+    Ok(())
+}
+
+fn inject_live_reload(html: &str) -> String {
+    match html.rfind("</body>") {
+        Some(index) => {
+            let mut html = html.to_string();
+            html.insert_str(index, LIVE_RELOAD_SCRIPT);
+            html
+        }
+        None => format!("{html}{LIVE_RELOAD_SCRIPT}"),
+    }
+}
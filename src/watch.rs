@@ -0,0 +1,66 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use notify::{RecursiveMode, Watcher};
+
+/// How long the filesystem has to stay quiet after the most recent event in
+/// a batch before triggering a rebuild.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Watches `root` for filesystem events, ignoring anything under `exclude`,
+/// and invokes `on_change` once per settled batch of events.
+///
+/// This drives the edit-rebuild-reload loop for `--watch`, running until the
+/// watcher shuts down or `on_change` returns an error.
+pub fn watch(
+    root: &Path,
+    exclude: &[PathBuf],
+    mut on_change: impl FnMut() -> Result<()>,
+) -> Result<()> {
+    // `notify` canonicalizes the watched root and always reports absolute
+    // paths in its events, so `exclude` (which callers build from relative
+    // paths) has to be canonicalized the same way or nothing will ever
+    // match.
+    let exclude: Vec<PathBuf> = exclude
+        .iter()
+        .map(|path| fs::canonicalize(path).unwrap_or_else(|_| path.clone()))
+        .collect();
+
+    let (tx, rx) = channel();
+    let mut watcher = notify::recommended_watcher(tx).context("failed to create file watcher")?;
+    watcher
+        .watch(root, RecursiveMode::Recursive)
+        .with_context(|| format!("failed to watch {root:?}"))?;
+
+    loop {
+        let Ok(event) = rx.recv() else {
+            break;
+        };
+
+        if !is_relevant(&event, &exclude) {
+            continue;
+        }
+
+        // Keep draining events until things settle for DEBOUNCE.
+        while rx.recv_timeout(DEBOUNCE).is_ok() {}
+
+        log::info!("Detected source changes, rebuilding");
+        on_change()?;
+    }
+
+    Ok(())
+}
+
+fn is_relevant(event: &notify::Result<notify::Event>, exclude: &[PathBuf]) -> bool {
+    let Ok(event) = event else {
+        return false;
+    };
+
+    !event
+        .paths
+        .iter()
+        .any(|path| exclude.iter().any(|excluded| path.starts_with(excluded)))
+}